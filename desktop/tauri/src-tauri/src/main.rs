@@ -1,46 +1,694 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write as _};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use once_cell::sync::Lazy;
-use tauri::{Manager, Runtime};
+use serde::Serialize;
+use tauri::api::process::{Command as SidecarCommand, CommandChild, CommandEvent};
+use tauri::{
+    CustomMenuItem, Manager, Runtime, State, SystemTray, SystemTrayEvent, SystemTrayMenu,
+    SystemTrayMenuItem,
+};
 
 static BACKEND: Lazy<Mutex<Option<Child>>> = Lazy::new(|| Mutex::new(None));
 
-fn spawn_backend() {
+/// Handle to the bundled backend sidecar, when one was launched instead of a
+/// system Python interpreter. Mutually exclusive with `BACKEND`.
+static SIDECAR: Lazy<Mutex<Option<CommandChild>>> = Lazy::new(|| Mutex::new(None));
+
+/// Python interpreter resolved at startup (`python3`/`python`); the supervisor
+/// spawns uvicorn through it. `None` until interpreter resolution succeeds.
+static PYTHON: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+/// Where the error dialog points users who are missing the backend deps.
+const SETUP_DOCS_URL: &str = "https://github.com/viper1331/Gestion-de-stock#setup";
+
+/// Port the backend is currently bound to, chosen dynamically at spawn time.
+/// Read by the health probe and mirrored into `BackendState` for the frontend.
+static BACKEND_PORT: Lazy<Mutex<Option<u16>>> = Lazy::new(|| Mutex::new(None));
+
+/// How many attempts in all to make at picking a free port / spawning, so a
+/// fresh port is tried if the OS-assigned one turns out to be unusable (the
+/// probe-listener → uvicorn bind race).
+const PORT_BIND_RETRIES: u32 = 3;
+
+/// Grace period after launch in which we watch for the child exiting early — a
+/// uvicorn that lost the port race dies almost immediately with "address in
+/// use", so a process still alive after this window has taken the port.
+const BIND_GRACE: Duration = Duration::from_millis(500);
+
+/// Roll the backend log over to `backend.log.1` once it grows past this size.
+const LOG_MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Managed state holding the resolved backend base URL so the frontend can ask
+/// for it via the `backend_url` command instead of assuming a fixed port.
+#[derive(Default)]
+struct BackendState {
+    url: Mutex<Option<String>>,
+}
+
+/// Base URL the dynamically-spawned backend is reachable at, e.g.
+/// `http://127.0.0.1:49160`. Empty until the backend has been launched.
+#[tauri::command]
+fn backend_url(state: State<'_, BackendState>) -> String {
+    state
+        .url
+        .lock()
+        .expect("lock backend url")
+        .clone()
+        .unwrap_or_default()
+}
+
+/// Base delay for the restart backoff; doubles on each consecutive failure.
+const BACKOFF_BASE: Duration = Duration::from_millis(500);
+/// Upper bound for the restart backoff.
+const BACKOFF_CAP: Duration = Duration::from_secs(30);
+/// How often the supervisor checks on the child once it is healthy.
+const SUPERVISOR_TICK: Duration = Duration::from_secs(2);
+/// Generous bounded wait for a freshly spawned backend to pass its readiness
+/// probe. A slow-but-alive backend must not be treated as a failure, so this is
+/// a single wait window, not a per-miss counter.
+const BOOT_TIMEOUT: Duration = Duration::from_secs(30);
+/// How often to re-probe while waiting for a freshly spawned backend to boot.
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Give up after this many consecutive failed respawns in a row.
+const MAX_CONSECUTIVE_FAILURES: u32 = 5;
+
+/// Coarse stages of bringing the backend up, each mapped to a 0.0–1.0 progress
+/// value so the webview can render a determinate splash/loading bar.
+#[derive(Clone, Copy)]
+enum Stage {
+    LaunchingInterpreter,
+    WaitingForPort,
+    Ready,
+}
+
+impl Stage {
+    fn progress(self) -> f64 {
+        match self {
+            Stage::LaunchingInterpreter => 0.1,
+            Stage::WaitingForPort => 0.5,
+            Stage::Ready => 1.0,
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            Stage::LaunchingInterpreter => "launching interpreter",
+            Stage::WaitingForPort => "waiting for port",
+            Stage::Ready => "backend ready",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Stage::LaunchingInterpreter => "launching",
+            Stage::WaitingForPort => "waiting",
+            Stage::Ready => "ready",
+        }
+    }
+}
+
+/// Structured startup-progress payload emitted on the `backend://status`
+/// channel so the frontend can drive a splash screen.
+#[derive(Clone, Serialize)]
+struct StatusPayload {
+    stage: &'static str,
+    progress: f64,
+    message: &'static str,
+}
+
+/// Emit a `backend://status` event for the given stage.
+fn emit_status<R: Runtime>(app_handle: &tauri::AppHandle<R>, stage: Stage) {
+    let _ = app_handle.emit_all(
+        "backend://status",
+        StatusPayload {
+            stage: stage.label(),
+            progress: stage.progress(),
+            message: stage.message(),
+        },
+    );
+}
+
+/// Candidate interpreter names, tried in order.
+const PYTHON_CANDIDATES: [&str; 2] = ["python3", "python"];
+
+/// Try each candidate interpreter and return the first one that can import the
+/// backend module, so a bare interpreter without the app installed is rejected.
+fn resolve_interpreter() -> Option<String> {
+    for candidate in PYTHON_CANDIDATES {
+        let ok = Command::new(candidate)
+            .args(["-c", "import backend.app"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false);
+        if ok {
+            return Some(candidate.to_string());
+        }
+    }
+    None
+}
+
+/// Best-effort detection of containerized/WSL environments, where the
+/// interpreter is usually somewhere other than the host PATH. Used to tailor
+/// the missing-dependency hint.
+fn environment_hint() -> Option<&'static str> {
+    if Path::new("/.dockerenv").exists() {
+        return Some(
+            "You appear to be running in a container; install Python inside the image \
+             or mount an interpreter on PATH.",
+        );
+    }
+    if let Ok(version) = fs::read_to_string("/proc/version") {
+        if version.to_lowercase().contains("microsoft") {
+            return Some(
+                "You appear to be running under WSL; install Python inside the WSL \
+                 distribution rather than on Windows.",
+            );
+        }
+    }
+    None
+}
+
+/// Show a native error dialog explaining the missing interpreter/backend and,
+/// if the user accepts, open the setup docs in their browser.
+fn report_missing_backend<R: Runtime>(app_handle: &tauri::AppHandle<R>) {
+    use tauri::api::dialog::blocking::MessageDialogBuilder;
+    use tauri::api::dialog::{MessageDialogButtons, MessageDialogKind};
+
+    let mut message = String::from(
+        "Could not find a Python interpreter with the backend installed. \
+         Install Python 3 and the project dependencies, then relaunch the app.",
+    );
+    if let Some(hint) = environment_hint() {
+        message.push_str("\n\n");
+        message.push_str(hint);
+    }
+
+    let open_docs = MessageDialogBuilder::new("Backend unavailable", message)
+        .kind(MessageDialogKind::Error)
+        .buttons(MessageDialogButtons::OkCancelCustom(
+            "Open setup docs".into(),
+            "Close".into(),
+        ))
+        .show();
+    if open_docs {
+        let _ = open::that(SETUP_DOCS_URL);
+    }
+    let _ = app_handle.emit_all("backend://failed", ());
+}
+
+/// Resolve the app log directory, creating it if necessary.
+fn log_dir<R: Runtime>(app_handle: &tauri::AppHandle<R>) -> Option<PathBuf> {
+    let dir = app_handle.path_resolver().app_log_dir()?;
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir)
+}
+
+/// Append a line to `path`, rotating to `path.1` first if the file has grown
+/// past `LOG_MAX_BYTES`. Best-effort: logging must never crash the supervisor.
+fn append_rotating(path: &Path, line: &str) {
+    if let Ok(meta) = fs::metadata(path) {
+        if meta.len() >= LOG_MAX_BYTES {
+            let _ = fs::rename(path, path.with_extension("log.1"));
+        }
+    }
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+        let _ = writeln!(file, "{line}");
+    }
+}
+
+/// Tee a backend output stream line-by-line into the rotating log file and
+/// forward each line to the webview on the `backend://log` channel.
+fn pipe_reader<R, S>(stream: S, source: &'static str, app_handle: tauri::AppHandle<R>, log_path: PathBuf)
+where
+    R: Runtime,
+    S: Read + Send + 'static,
+{
+    std::thread::spawn(move || {
+        let reader = BufReader::new(stream);
+        for line in reader.lines().map_while(Result::ok) {
+            let entry = format!("[{source}] {line}");
+            append_rotating(&log_path, &entry);
+            let _ = app_handle.emit_all("backend://log", entry);
+        }
+    });
+}
+
+/// Ask the OS for a free port by binding to `:0`, then immediately drop the
+/// listener so uvicorn can take it. There is a small race between dropping the
+/// listener and uvicorn binding; the caller retries on failure.
+fn pick_free_port() -> Option<u16> {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).ok()?;
+    let port = listener.local_addr().ok()?.port();
+    drop(listener);
+    Some(port)
+}
+
+/// Whether a bundled sidecar binary actually exists next to the app executable.
+/// Mirrors Tauri's own sidecar resolution (`<exe dir>/backend<EXE_SUFFIX>`) so
+/// we distinguish a packaged build from a dev machine where `externalBin` is
+/// configured but the binary was never produced.
+fn sidecar_binary_exists() -> bool {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        .map(|dir| dir.join(format!("backend{}", std::env::consts::EXE_SUFFIX)))
+        .map(|path| path.exists())
+        .unwrap_or(false)
+}
+
+/// Launch the bundled backend sidecar (a PyInstaller-packaged executable wired
+/// in as `externalBin`), on a dynamically chosen free port. Returns the port on
+/// success, or `None` when no sidecar is bundled (dev mode) so the caller can
+/// fall back to a system Python interpreter. Its stdout/stderr are teed to the
+/// same rotating log and `backend://log` channel as the system-python path.
+fn spawn_sidecar<R: Runtime>(app_handle: &tauri::AppHandle<R>) -> Option<u16> {
+    let log_path = log_dir(app_handle).map(|dir| dir.join("backend.log"));
+    for _ in 0..PORT_BIND_RETRIES {
+        let port = match pick_free_port() {
+            Some(port) => port,
+            None => continue,
+        };
+        let command = match SidecarCommand::new_sidecar("backend") {
+            Ok(command) => command.args([
+                "--host",
+                "127.0.0.1",
+                "--port",
+                &port.to_string(),
+            ]),
+            // No sidecar bundled: signal the caller to fall back to system python.
+            Err(_) => return None,
+        };
+        let (mut rx, child) = match command.spawn() {
+            Ok(pair) => pair,
+            Err(_) => continue,
+        };
+
+        let event_handle = app_handle.clone();
+        let event_log = log_path.clone();
+        tauri::async_runtime::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                match event {
+                    CommandEvent::Stdout(line) | CommandEvent::Stderr(line) => {
+                        let entry = format!("[sidecar] {}", line.trim_end());
+                        if let Some(path) = &event_log {
+                            append_rotating(path, &entry);
+                        }
+                        let _ = event_handle.emit_all("backend://log", entry);
+                    }
+                    CommandEvent::Terminated(_) => {
+                        // Let the supervisor notice the gap and respawn.
+                        *SIDECAR.lock().expect("lock sidecar process") = None;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        *SIDECAR.lock().expect("lock sidecar process") = Some(child);
+        *BACKEND_PORT.lock().expect("lock backend port") = Some(port);
+
+        // Watch for an early exit: if the sidecar lost the port race its event
+        // loop clears `SIDECAR` on `Terminated`; retry with a fresh port.
+        std::thread::sleep(BIND_GRACE);
+        if SIDECAR.lock().expect("lock sidecar process").is_some() {
+            return Some(port);
+        }
+    }
+    None
+}
+
+/// Spawn the backend on a dynamically chosen free port and record that port for
+/// the health probe. Prefers the bundled sidecar and falls back to a system
+/// Python interpreter in dev mode. Returns the chosen port on success so the
+/// supervisor can publish the resolved URL to the frontend.
+fn spawn_backend<R: Runtime>(app_handle: &tauri::AppHandle<R>) -> Option<u16> {
     let mut guard = BACKEND.lock().expect("lock backend process");
-    if guard.is_some() {
-        return;
+    if guard.is_some() || SIDECAR.lock().expect("lock sidecar process").is_some() {
+        return *BACKEND_PORT.lock().expect("lock backend port");
+    }
+    if let Some(port) = spawn_sidecar(app_handle) {
+        return Some(port);
+    }
+    let log_path = log_dir(app_handle).map(|dir| dir.join("backend.log"));
+    for _ in 0..PORT_BIND_RETRIES {
+        let port = match pick_free_port() {
+            Some(port) => port,
+            None => continue,
+        };
+        let interpreter = PYTHON
+            .lock()
+            .expect("lock python interpreter")
+            .clone()
+            .unwrap_or_else(|| "python".to_string());
+        let mut child = match Command::new(interpreter)
+            .args([
+                "-m",
+                "uvicorn",
+                "backend.app:app",
+                "--host",
+                "127.0.0.1",
+                "--port",
+                &port.to_string(),
+            ])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(_) => continue,
+        };
+
+        // uvicorn exits almost immediately if the port was taken between our
+        // probe-listener drop and its bind; watch for that before committing.
+        std::thread::sleep(BIND_GRACE);
+        if matches!(child.try_wait(), Ok(Some(_)) | Err(_)) {
+            let _ = child.kill();
+            continue;
+        }
+
+        if let Some(log_path) = log_path.clone() {
+            if let Some(stdout) = child.stdout.take() {
+                pipe_reader(stdout, "out", app_handle.clone(), log_path.clone());
+            }
+            if let Some(stderr) = child.stderr.take() {
+                pipe_reader(stderr, "err", app_handle.clone(), log_path);
+            }
+        }
+        *guard = Some(child);
+        *BACKEND_PORT.lock().expect("lock backend port") = Some(port);
+        return Some(port);
     }
-    let child = Command::new("python")
-        .args(["-m", "uvicorn", "backend.app:app", "--host", "127.0.0.1", "--port", "8000"])
-        .stdout(Stdio::null())
-        .stderr(Stdio::null())
-        .spawn()
-        .ok();
-    *guard = child;
+    None
 }
 
 fn kill_backend() {
     if let Some(child) = BACKEND.lock().expect("lock backend process").as_mut() {
         let _ = child.kill();
+        // Reap the child so a killed-but-unwaited process doesn't linger as a
+        // zombie once `clear_backend` drops the handle.
+        let _ = child.wait();
+    }
+    if let Some(child) = SIDECAR.lock().expect("lock sidecar process").take() {
+        let _ = child.kill();
+    }
+}
+
+/// Kill the backend from the panic hook, tolerating poisoned mutexes. A panic
+/// while a backend guard was held poisons that lock, and a plain `.expect`
+/// would panic again inside the hook and abort before the child is reaped —
+/// recover the inner value so the kill always runs.
+fn force_kill_backend() {
+    if let Some(child) = BACKEND
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_mut()
+    {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    if let Some(child) = SIDECAR
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+    {
+        let _ = child.kill();
+    }
+}
+
+/// Returns `true` if the backend has exited on its own (or is gone) since we
+/// last looked. Keeps the guard hold short so the supervisor never blocks
+/// shutdown. A live sidecar counts as running; its termination is observed via
+/// the sidecar event loop, which clears `SIDECAR`.
+fn backend_exited() -> bool {
+    if SIDECAR.lock().expect("lock sidecar process").is_some() {
+        return false;
+    }
+    let mut guard = BACKEND.lock().expect("lock backend process");
+    match guard.as_mut() {
+        Some(child) => matches!(child.try_wait(), Ok(Some(_)) | Err(_)),
+        None => true,
+    }
+}
+
+/// Clear the handles so the next `spawn_backend()` starts a fresh process.
+fn clear_backend() {
+    *BACKEND.lock().expect("lock backend process") = None;
+    *SIDECAR.lock().expect("lock sidecar process") = None;
+}
+
+/// Minimal HTTP readiness probe against the backend. We never touch the
+/// `BACKEND` mutex here, so a probe can run while the supervisor holds nothing.
+fn backend_healthy() -> bool {
+    let port = match *BACKEND_PORT.lock().expect("lock backend port") {
+        Some(port) => port,
+        None => return false,
+    };
+    let mut stream = match TcpStream::connect(("127.0.0.1", port)) {
+        Ok(stream) => stream,
+        Err(_) => return false,
+    };
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(2)));
+    let _ = stream.set_write_timeout(Some(Duration::from_secs(2)));
+    if stream
+        .write_all(b"GET /health HTTP/1.0\r\nHost: 127.0.0.1\r\nConnection: close\r\n\r\n")
+        .is_err()
+    {
+        return false;
+    }
+    let mut response = String::new();
+    if stream.read_to_string(&mut response).is_err() {
+        return false;
+    }
+    // A well-formed status line means the port is owned by a live HTTP server;
+    // the absence of one is what counts as "not ready yet".
+    response.starts_with("HTTP/")
+}
+
+/// Run the blocking readiness probe off the async worker so the (tokio) runtime
+/// powering the sidecar event loop is never pinned.
+async fn probe_healthy() -> bool {
+    tauri::async_runtime::spawn_blocking(backend_healthy)
+        .await
+        .unwrap_or(false)
+}
+
+/// Wait for a freshly spawned backend to pass its readiness probe, up to
+/// `BOOT_TIMEOUT`. Returns `true` once healthy, or `false` if the child exits
+/// first or the window elapses — both of which the caller treats as a failed
+/// respawn. Probe misses against a still-booting child are NOT failures.
+async fn wait_until_healthy() -> bool {
+    let start = Instant::now();
+    loop {
+        if probe_healthy().await {
+            return true;
+        }
+        if backend_exited() || start.elapsed() >= BOOT_TIMEOUT {
+            return false;
+        }
+        tokio::time::sleep(HEALTH_POLL_INTERVAL).await;
+    }
+}
+
+/// Reveal the main window and dismiss the splash. Used both on a successful
+/// `Ready` transition and on terminal failure, so the error UI is never stranded
+/// behind a hidden window.
+fn reveal_main<R: Runtime>(app_handle: &tauri::AppHandle<R>) {
+    if let Some(window) = app_handle.get_window("main") {
+        let _ = window.show();
+    }
+    if let Some(splash) = app_handle.get_window("splashscreen") {
+        let _ = splash.close();
+    }
+}
+
+/// Supervise the backend child: respawn on unexpected exit with exponential
+/// backoff, and gate "healthy" on an HTTP readiness probe. The terminal
+/// failure counter is scoped to failed respawns in a row — a spawn that never
+/// launches, or one that is launched but never reaches health within
+/// `BOOT_TIMEOUT` (crash/bind loss). A slow-but-alive backend never trips it.
+/// After `MAX_CONSECUTIVE_FAILURES` such failures we emit a terminal
+/// `backend://failed` event so the UI can surface an error.
+fn supervise_backend<R: Runtime>(app_handle: tauri::AppHandle<R>) {
+    tauri::async_runtime::spawn(async move {
+        let mut backoff = BACKOFF_BASE;
+        let mut failures: u32 = 0;
+        // Only walk the UI through the startup stages once, on the first
+        // healthy transition; later respawns are handled silently.
+        let mut signalled_ready = false;
+
+        loop {
+            if !backend_exited() {
+                // Steady state: the backend is alive, just idle and re-check.
+                tokio::time::sleep(SUPERVISOR_TICK).await;
+                continue;
+            }
+
+            clear_backend();
+            if !signalled_ready {
+                emit_status(&app_handle, Stage::LaunchingInterpreter);
+            }
+
+            // spawn_backend can briefly block (process launch + bind check), so
+            // run it off the async worker.
+            let spawn_handle = app_handle.clone();
+            let port = tauri::async_runtime::spawn_blocking(move || spawn_backend(&spawn_handle))
+                .await
+                .unwrap_or(None);
+            let Some(port) = port else {
+                failures += 1;
+                if failures >= MAX_CONSECUTIVE_FAILURES {
+                    reveal_main(&app_handle);
+                    let _ = app_handle.emit_all("backend://failed", ());
+                    return;
+                }
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(BACKOFF_CAP);
+                continue;
+            };
+            *app_handle.state::<BackendState>().url.lock().expect("lock backend url") =
+                Some(format!("http://127.0.0.1:{port}"));
+            if !signalled_ready {
+                emit_status(&app_handle, Stage::WaitingForPort);
+            }
+
+            if wait_until_healthy().await {
+                failures = 0;
+                backoff = BACKOFF_BASE;
+                if !signalled_ready {
+                    signalled_ready = true;
+                    emit_status(&app_handle, Stage::Ready);
+                    reveal_main(&app_handle);
+                }
+            } else {
+                // Launched but never reached health (crash or lost port race):
+                // a failed respawn. Tear it down and back off before retrying.
+                kill_backend();
+                clear_backend();
+                failures += 1;
+                if failures >= MAX_CONSECUTIVE_FAILURES {
+                    reveal_main(&app_handle);
+                    let _ = app_handle.emit_all("backend://failed", ());
+                    return;
+                }
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(BACKOFF_CAP);
+            }
+        }
+    });
+}
+
+/// Install a panic hook that writes the panic message and a backtrace to
+/// `crash.log` next to the backend logs and kills the uvicorn child, so a
+/// Rust-side crash never leaves the backend orphaned. The previous hook is
+/// chained afterwards to preserve the default console output.
+fn install_panic_hook<R: Runtime>(app_handle: tauri::AppHandle<R>) {
+    let crash_path = log_dir(&app_handle).map(|dir| dir.join("crash.log"));
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Some(path) = &crash_path {
+            let backtrace = std::backtrace::Backtrace::force_capture();
+            if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+                let _ = writeln!(file, "{info}\n{backtrace}");
+            }
+        }
+        force_kill_backend();
+        previous(info);
+    }));
+}
+
+/// Build the tray menu: show the window, restart the backend, or quit.
+fn build_tray() -> SystemTray {
+    let menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new("show", "Show"))
+        .add_item(CustomMenuItem::new("restart", "Restart backend"))
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new("quit", "Quit"));
+    SystemTray::new().with_menu(menu)
+}
+
+/// Handle tray interactions. Only "Quit" tears down the backend and exits;
+/// "Restart backend" kills the child so the supervisor respawns it, and "Show"
+/// (or a left click) reveals the hidden window.
+fn on_tray_event<R: Runtime>(app: &tauri::AppHandle<R>, event: SystemTrayEvent) {
+    match event {
+        SystemTrayEvent::LeftClick { .. } => {
+            if let Some(window) = app.get_window("main") {
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+        }
+        SystemTrayEvent::MenuItemClick { id, .. } => match id.as_str() {
+            "show" => {
+                if let Some(window) = app.get_window("main") {
+                    let _ = window.show();
+                    let _ = window.set_focus();
+                }
+            }
+            "restart" => {
+                // Drop the child; the supervisor detects the exit and respawns.
+                kill_backend();
+                clear_backend();
+            }
+            "quit" => {
+                kill_backend();
+                app.exit(0);
+            }
+            _ => {}
+        },
+        _ => {}
     }
 }
 
 fn main() {
     tauri::Builder::default()
+        .manage(BackendState::default())
+        .invoke_handler(tauri::generate_handler![backend_url])
+        .system_tray(build_tray())
+        .on_system_tray_event(on_tray_event)
         .setup(|app| {
-            spawn_backend();
             let app_handle = app.handle();
-            app_handle.listen_global("tauri://close-requested", move |_| {
-                kill_backend();
-            });
+            install_panic_hook(app_handle.clone());
+            // A bundled sidecar needs no system interpreter. Gate on the binary
+            // actually existing on disk — `new_sidecar` succeeds whenever the
+            // sidecar is merely *configured* in `externalBin`, which would take
+            // the production branch on a dev machine where the binary was never
+            // built. Only when no sidecar binary is present (dev mode) do we
+            // resolve `python3`/`python` up front and, if nothing can import the
+            // backend, show a native error instead of spinning on a dead child.
+            if sidecar_binary_exists() {
+                supervise_backend(app_handle.clone());
+            } else {
+                match resolve_interpreter() {
+                    Some(interpreter) => {
+                        *PYTHON.lock().expect("lock python interpreter") = Some(interpreter);
+                        // The supervisor owns the launch so it can emit the
+                        // startup stages (`backend://status`) and reveal the
+                        // window once the probe passes.
+                        supervise_backend(app_handle.clone());
+                    }
+                    None => report_missing_backend(&app_handle),
+                }
+            }
             Ok(())
         })
         .on_window_event(|event| {
-            if let tauri::WindowEvent::Destroyed = event.event() {
-                kill_backend();
+            // Closing the window only hides it: the FastAPI server and any
+            // in-progress stock operations keep running. Quitting from the tray
+            // is the only path that kills the backend.
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event.event() {
+                api.prevent_close();
+                let _ = event.window().hide();
             }
         })
         .run(tauri::generate_context!())